@@ -1,4 +1,4 @@
-use std::{fs::{FileType, Metadata}, path::Path, time::SystemTime};
+use std::{fs::{FileType, Metadata}, future::Future, path::Path, time::SystemTime};
 
 use bitflags::bitflags;
 use yazi_macro::{unix_either, win_either};
@@ -15,9 +15,67 @@ bitflags! {
 		const DUMMY  = 0b00010000;
 		#[cfg(windows)]
 		const SYSTEM = 0b00100000;
+		#[cfg(target_os = "linux")]
+		const XATTR  = 0b01000000;
 	}
 }
 
+bitflags! {
+	/// Which of [`Cha`]'s fields a [`ChaSource`] is actually able to
+	/// populate. [`Cha::hits_masked`] restricts its comparison to this
+	/// set, so a source that can't report e.g. `ctime` doesn't cause
+	/// spurious change detection against one that can.
+	#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+	pub struct ChaMask: u8 {
+		const LEN   = 0b00001;
+		const MTIME = 0b00010;
+		#[cfg(unix)]
+		const CTIME = 0b00100;
+		const BTIME = 0b01000;
+		#[cfg(unix)]
+		const MODE  = 0b10000;
+	}
+}
+
+/// A source of file metadata that can be converted into a [`Cha`]. The
+/// local filesystem — via [`Metadata`] and [`FileType`] below — is the
+/// only implementation today, but this is the seam a future remote
+/// backend hangs off of: a 9P mount's `Rstat`/`getattr` reply already
+/// carries a qid type (for `kind`), mode, length, atime/mtime/ctime,
+/// uid/gid, nlink, and the server's inode-equivalent, so it can implement
+/// this trait directly instead of having to fabricate a
+/// `std::fs::Metadata` just to reuse [`Cha::new_nofollow`].
+pub trait ChaSource {
+	/// Which fields this source can actually populate.
+	const MASK: ChaMask;
+
+	/// Whether this is the local filesystem — only local sources are
+	/// eligible for the `statx(2)` fast path on Linux.
+	#[cfg(target_os = "linux")]
+	const LOCAL: bool = false;
+
+	fn is_symlink(&self) -> bool;
+
+	/// Re-fetches this source following the symlink it points to, used by
+	/// [`Cha::new`] to resolve what a symlink targets. The default is a
+	/// no-op, for sources (like a bare [`FileType`]) with nothing further
+	/// to fetch.
+	#[allow(unused_variables)]
+	fn follow(self, path: &Path) -> impl Future<Output = Self>
+	where
+		Self: Sized,
+	{
+		async { self }
+	}
+
+	#[cfg(windows)]
+	fn is_hidden_attr(&self) -> bool { false }
+	#[cfg(windows)]
+	fn is_system_attr(&self) -> bool { false }
+
+	fn into_cha(self) -> Cha;
+}
+
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub struct Cha {
 	pub kind:  ChaKind,
@@ -37,6 +95,10 @@ pub struct Cha {
 	pub gid:   libc::gid_t,
 	#[cfg(unix)]
 	pub nlink: libc::nlink_t,
+	#[cfg(unix)]
+	pub ino:   libc::ino_t,
+	#[cfg(unix)]
+	pub rdev:  libc::dev_t,
 }
 
 impl From<Metadata> for Cha {
@@ -85,6 +147,16 @@ impl From<Metadata> for Cha {
 				use std::os::unix::fs::MetadataExt;
 				m.nlink() as _
 			},
+			#[cfg(unix)]
+			ino: {
+				use std::os::unix::fs::MetadataExt;
+				m.ino() as _
+			},
+			#[cfg(unix)]
+			rdev: {
+				use std::os::unix::fs::MetadataExt;
+				m.rdev() as _
+			},
 		}
 	}
 }
@@ -133,26 +205,94 @@ impl From<FileType> for Cha {
 	}
 }
 
+impl ChaSource for Metadata {
+	const MASK: ChaMask = ChaMask::all();
+	#[cfg(target_os = "linux")]
+	const LOCAL: bool = true;
+
+	fn is_symlink(&self) -> bool { Metadata::is_symlink(self) }
+
+	async fn follow(self, path: &Path) -> Self {
+		tokio::fs::metadata(path).await.unwrap_or(self)
+	}
+
+	#[cfg(windows)]
+	fn is_hidden_attr(&self) -> bool {
+		use std::os::windows::fs::MetadataExt;
+
+		use windows_sys::Win32::Storage::FileSystem::FILE_ATTRIBUTE_HIDDEN;
+		self.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0
+	}
+
+	#[cfg(windows)]
+	fn is_system_attr(&self) -> bool {
+		use std::os::windows::fs::MetadataExt;
+
+		use windows_sys::Win32::Storage::FileSystem::FILE_ATTRIBUTE_SYSTEM;
+		self.file_attributes() & FILE_ATTRIBUTE_SYSTEM != 0
+	}
+
+	fn into_cha(self) -> Cha { Cha::from(self) }
+}
+
+impl ChaSource for FileType {
+	// A bare `FileType` carries no length/timestamps at all, and only the
+	// file-type bits of `mode`, so no masked field is reported; with this
+	// mask `hits_masked()` compares `kind` alone.
+	const MASK: ChaMask = ChaMask::empty();
+
+	fn is_symlink(&self) -> bool { FileType::is_symlink(self) }
+
+	fn into_cha(self) -> Cha { Cha::from(self) }
+}
+
 impl Cha {
+	/// Builds a `Cha` from a source that has already been stat'd once
+	/// (e.g. the entry a directory walk just read). If it's a symlink,
+	/// this resolves it — on Linux, via a single extra `statx(2)` call
+	/// when `S::LOCAL`, the same fast path [`Self::stat_nofollow`] uses;
+	/// otherwise via [`ChaSource::follow`], which for [`Metadata`] costs
+	/// a second `tokio::fs::metadata` round-trip.
 	#[inline]
-	pub async fn new(path: &Path, mut meta: Metadata) -> Self {
+	pub async fn new<S: ChaSource>(path: &Path, mut stat: S) -> Self {
 		let mut attached = ChaKind::empty();
-
-		if meta.is_symlink() {
+		let symlink = stat.is_symlink();
+		if symlink {
 			attached |= ChaKind::LINK;
-			meta = tokio::fs::metadata(path).await.unwrap_or(meta);
+
+			#[cfg(target_os = "linux")]
+			if S::LOCAL {
+				match linux::stat(path, false) {
+					linux::Statx::Ok(mut cha) => {
+						cha.kind |= attached;
+						if yazi_shared::url::Urn::new(path).is_hidden() {
+							cha.kind |= ChaKind::HIDDEN;
+						}
+						return cha;
+					}
+					linux::Statx::Err => attached |= ChaKind::ORPHAN,
+					linux::Statx::Unsupported => {}
+				}
+			}
+
+			stat = stat.follow(path).await;
 		}
-		if meta.is_symlink() {
+		if stat.is_symlink() {
 			attached |= ChaKind::ORPHAN;
 		}
 
-		let mut cha = Self::new_nofollow(path, meta);
+		let mut cha = Self::new_nofollow(path, stat);
 		cha.kind |= attached;
 		cha
 	}
 
+	/// Builds a `Cha` directly from `stat`, which the caller must already
+	/// have obtained — this never issues its own syscall, so it never
+	/// duplicates the one the caller already paid for. For a fresh,
+	/// single-syscall nofollow stat on Linux, use [`Self::stat_nofollow`]
+	/// instead.
 	#[inline]
-	pub fn new_nofollow(_path: &Path, meta: Metadata) -> Self {
+	pub fn new_nofollow<S: ChaSource>(_path: &Path, stat: S) -> Self {
 		let mut attached = ChaKind::empty();
 
 		#[cfg(unix)]
@@ -161,33 +301,77 @@ impl Cha {
 		}
 		#[cfg(windows)]
 		{
-			use std::os::windows::fs::MetadataExt;
-
-			use windows_sys::Win32::Storage::FileSystem::{FILE_ATTRIBUTE_HIDDEN, FILE_ATTRIBUTE_SYSTEM};
-			if meta.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0 {
+			if stat.is_hidden_attr() {
 				attached |= ChaKind::HIDDEN;
 			}
-			if meta.file_attributes() & FILE_ATTRIBUTE_SYSTEM != 0 {
+			if stat.is_system_attr() {
 				attached |= ChaKind::SYSTEM;
 			}
 		}
 
-		let mut cha = Self::from(meta);
+		let mut cha = stat.into_cha();
 		cha.kind |= attached;
 		cha
 	}
 
+	/// Stats `path` without following symlinks and builds a `Cha` from it,
+	/// for callers that don't already have a [`Metadata`] in hand. On
+	/// Linux this is a single `statx(2)` call when that succeeds; if it
+	/// fails, and on other platforms, it uses [`std::fs::symlink_metadata`].
+	#[inline]
+	pub fn stat_nofollow(path: &Path) -> std::io::Result<Self> {
+		#[cfg(target_os = "linux")]
+		if let linux::Statx::Ok(mut cha) = linux::stat(path, true) {
+			if yazi_shared::url::Urn::new(path).is_hidden() {
+				cha.kind |= ChaKind::HIDDEN;
+			}
+			return Ok(cha);
+		}
+
+		std::fs::symlink_metadata(path).map(|meta| Self::new_nofollow(path, meta))
+	}
+
+	/// Like [`Self::new_nofollow`], but additionally fetches `path`'s
+	/// extended attributes (xattrs, POSIX ACLs, file capabilities). This
+	/// costs a handful of extra syscalls, so callers must opt in rather
+	/// than pay for it on every entry of an ordinary listing.
+	#[cfg(target_os = "linux")]
+	#[inline]
+	pub fn new_nofollow_with_xattr(path: &Path, meta: Metadata) -> (Self, Option<ChaXattr>) {
+		let mut cha = Self::new_nofollow(path, meta);
+		let xattr = linux::xattr::fetch(path);
+		if xattr.is_some() {
+			cha.kind |= ChaKind::XATTR;
+		}
+		(cha, xattr)
+	}
+
 	#[inline]
 	pub fn dummy() -> Self { Self { kind: ChaKind::DUMMY, ..Default::default() } }
 
 	#[inline]
-	pub fn hits(self, c: Self) -> bool {
-		self.len == c.len
-			&& self.mtime == c.mtime
-			&& unix_either!(self.ctime == c.ctime, true)
-			&& self.btime == c.btime
+	pub fn hits(self, c: Self) -> bool { self.hits_masked(c, ChaMask::all()) }
+
+	/// Like [`Self::hits`], but only compares the fields set in `mask` —
+	/// use [`ChaSource::MASK`] when comparing `Cha`s that may have come
+	/// from a source that can't populate every field.
+	#[inline]
+	pub fn hits_masked(self, c: Self, mask: ChaMask) -> bool {
+		(!mask.contains(ChaMask::LEN) || self.len == c.len)
+			&& (!mask.contains(ChaMask::MTIME) || self.mtime == c.mtime)
+			&& unix_either!(!mask.contains(ChaMask::CTIME) || self.ctime == c.ctime, true)
+			&& (!mask.contains(ChaMask::BTIME) || self.btime == c.btime)
 			&& self.kind == c.kind
-			&& unix_either!(self.mode == c.mode, true)
+			&& unix_either!(!mask.contains(ChaMask::MODE) || self.mode == c.mode, true)
+	}
+
+	/// The `(dev, ino)` pair that uniquely identifies the physical file this
+	/// `Cha` points at, or `None` if it's not worth tracking for dedup —
+	/// i.e. it has no other hardlinks.
+	#[cfg(unix)]
+	#[inline]
+	pub fn ident(&self) -> Option<(libc::dev_t, libc::ino_t)> {
+		(self.nlink > 1).then_some((self.dev, self.ino))
 	}
 }
 
@@ -209,6 +393,10 @@ impl Cha {
 	#[inline]
 	pub const fn is_dummy(&self) -> bool { self.kind.contains(ChaKind::DUMMY) }
 
+	#[cfg(target_os = "linux")]
+	#[inline]
+	pub const fn is_xattr(&self) -> bool { self.kind.contains(ChaKind::XATTR) }
+
 	#[inline]
 	pub const fn is_block(&self) -> bool {
 		unix_either!(self.mode & libc::S_IFMT == libc::S_IFBLK, false)
@@ -229,9 +417,494 @@ impl Cha {
 		unix_either!(self.mode & libc::S_IFMT == libc::S_IFSOCK, false)
 	}
 
+	/// The major number of the device this special file represents, as
+	/// shown by `ls -l` in place of a size for block/char device entries.
+	#[cfg(unix)]
+	#[inline]
+	pub const fn dev_major(&self) -> u32 { libc::major(self.rdev) as u32 }
+
+	/// The minor number of the device this special file represents.
+	#[cfg(unix)]
+	#[inline]
+	pub const fn dev_minor(&self) -> u32 { libc::minor(self.rdev) as u32 }
+
 	#[inline]
 	pub const fn is_exec(&self) -> bool { unix_either!(self.mode & libc::S_IXUSR != 0, false) }
 
 	#[inline]
 	pub const fn is_sticky(&self) -> bool { unix_either!(self.mode & libc::S_ISVTX != 0, false) }
 }
+
+/// Tracks which hardlinked files have already been counted during a
+/// recursive walk, so a directory's apparent size matches its real
+/// on-disk size instead of double-counting every extra link.
+#[cfg(unix)]
+#[derive(Default)]
+pub struct HardlinkSet(std::collections::HashSet<(libc::dev_t, libc::ino_t)>);
+
+#[cfg(unix)]
+impl HardlinkSet {
+	pub fn new() -> Self { Self::default() }
+
+	/// Feeds a `Cha` into the set, returning `true` if this is the first
+	/// time its underlying file has been seen (i.e. it should be counted).
+	pub fn insert(&mut self, cha: &Cha) -> bool {
+		match cha.ident() {
+			Some(ident) => self.0.insert(ident),
+			None => true,
+		}
+	}
+}
+
+/// A file's extended-attribute set, fetched only when a caller opts in
+/// through [`Cha::new_nofollow_with_xattr`]. The well-known Linux
+/// namespaces are decoded into typed fields; everything else under
+/// `user.*` is kept as raw name→bytes pairs.
+#[cfg(target_os = "linux")]
+#[derive(Clone, Debug, Default)]
+pub struct ChaXattr {
+	pub acl_access:  Option<Acl>,
+	pub acl_default: Option<Acl>,
+	pub fcaps:       Option<FileCapabilities>,
+	pub user:        Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+/// A single POSIX ACL entry, as decoded from `system.posix_acl_{access,default}`.
+#[cfg(target_os = "linux")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AclEntry {
+	pub tag:  AclTag,
+	pub id:   u32,
+	pub perm: u8,
+}
+
+#[cfg(target_os = "linux")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AclTag {
+	UserObj,
+	User,
+	GroupObj,
+	Group,
+	Mask,
+	Other,
+}
+
+#[cfg(target_os = "linux")]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Acl {
+	pub entries: Vec<AclEntry>,
+}
+
+/// A Linux file-capability set, as decoded from `security.capability`.
+#[cfg(target_os = "linux")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FileCapabilities {
+	pub permitted:   u64,
+	pub inheritable: u64,
+	pub effective:   bool,
+	/// The root uid the capabilities are relative to, for version-3 sets
+	/// written with `VFS_CAP_REVISION_3` (namespaced capabilities).
+	pub root_uid:    Option<u32>,
+}
+
+// `std::fs::Metadata` can't surface `btime` on most Linux filesystems, and
+// following a symlink needs a second `tokio::fs::metadata` round-trip. A raw
+// `statx(2)` call gets both in one syscall, so we try it first and fall back
+// to the portable path when it fails or the kernel doesn't support it.
+#[cfg(target_os = "linux")]
+mod linux {
+	use std::{
+		mem::MaybeUninit,
+		os::unix::ffi::OsStrExt,
+		path::Path,
+		sync::atomic::{AtomicBool, Ordering},
+		time::{Duration, SystemTime, UNIX_EPOCH},
+	};
+
+	use super::{Cha, ChaKind};
+
+	// `statx(2)` landed in Linux 4.11; older kernels report `ENOSYS`, and
+	// some sandboxes/seccomp filters report `EINVAL`. Remember that once so
+	// we don't pay for a failing syscall on every single entry.
+	static UNSUPPORTED: AtomicBool = AtomicBool::new(false);
+
+	pub(super) enum Statx {
+		Ok(Cha),
+		// The path itself is broken (e.g. a dangling symlink); statx is fine.
+		Err,
+		// statx(2) isn't usable on this kernel; fall back to std::fs.
+		Unsupported,
+	}
+
+	pub(super) fn stat(path: &Path, nofollow: bool) -> Statx {
+		if UNSUPPORTED.load(Ordering::Relaxed) {
+			return Statx::Unsupported;
+		}
+
+		let Ok(c_path) = std::ffi::CString::new(path.as_os_str().as_bytes()) else {
+			return Statx::Err;
+		};
+
+		let mut flags = libc::AT_STATX_SYNC_AS_STAT;
+		if nofollow {
+			flags |= libc::AT_SYMLINK_NOFOLLOW;
+		}
+
+		let mut buf = MaybeUninit::<libc::statx>::zeroed();
+		let ret = unsafe {
+			libc::statx(
+				libc::AT_FDCWD,
+				c_path.as_ptr(),
+				flags,
+				libc::STATX_BASIC_STATS | libc::STATX_BTIME,
+				buf.as_mut_ptr(),
+			)
+		};
+
+		if ret != 0 {
+			return match std::io::Error::last_os_error().raw_os_error() {
+				Some(libc::ENOSYS) | Some(libc::EINVAL) => {
+					UNSUPPORTED.store(true, Ordering::Relaxed);
+					Statx::Unsupported
+				}
+				_ => Statx::Err,
+			};
+		}
+
+		Statx::Ok(from_statx(unsafe { buf.assume_init() }))
+	}
+
+	fn timestamp(ts: libc::statx_timestamp) -> Option<SystemTime> {
+		// `tv_nsec` is always a non-negative forward offset, even when
+		// `tv_sec` is negative (a pre-1970 timestamp), so a naive
+		// `tv_sec as u64` would wrap a negative value into a multi-billion-
+		// year duration instead of subtracting it from the epoch.
+		if ts.tv_sec >= 0 {
+			UNIX_EPOCH.checked_add(Duration::new(ts.tv_sec as u64, ts.tv_nsec))
+		} else {
+			UNIX_EPOCH
+				.checked_sub(Duration::new(ts.tv_sec.unsigned_abs(), 0))
+				.and_then(|t| t.checked_add(Duration::from_nanos(ts.tv_nsec as u64)))
+		}
+	}
+
+	fn from_statx(buf: libc::statx) -> Cha {
+		let mode = buf.stx_mode as libc::mode_t;
+
+		let mut kind = ChaKind::empty();
+		if mode & libc::S_IFMT == libc::S_IFDIR {
+			kind |= ChaKind::DIR;
+		} else if mode & libc::S_IFMT == libc::S_IFLNK {
+			kind |= ChaKind::LINK;
+		}
+
+		Cha {
+			kind,
+			len: buf.stx_size,
+			atime: timestamp(buf.stx_atime),
+			btime: if buf.stx_mask & libc::STATX_BTIME != 0 { timestamp(buf.stx_btime) } else { None },
+			ctime: timestamp(buf.stx_ctime),
+			mtime: timestamp(buf.stx_mtime),
+			mode,
+			dev: libc::makedev(buf.stx_dev_major, buf.stx_dev_minor),
+			uid: buf.stx_uid,
+			gid: buf.stx_gid,
+			nlink: buf.stx_nlink as _,
+			ino: buf.stx_ino,
+			rdev: libc::makedev(buf.stx_rdev_major, buf.stx_rdev_minor),
+		}
+	}
+
+	#[cfg(test)]
+	mod tests {
+		use std::mem::MaybeUninit;
+
+		use super::*;
+
+		fn statx_timestamp(tv_sec: i64, tv_nsec: u32) -> libc::statx_timestamp {
+			let mut ts: libc::statx_timestamp = unsafe { MaybeUninit::zeroed().assume_init() };
+			ts.tv_sec = tv_sec;
+			ts.tv_nsec = tv_nsec;
+			ts
+		}
+
+		#[test]
+		fn from_statx_maps_fields_and_masks_btime() {
+			let mut buf: libc::statx = unsafe { MaybeUninit::zeroed().assume_init() };
+			buf.stx_mask = libc::STATX_BASIC_STATS;
+			buf.stx_mode = libc::S_IFDIR as u16 | 0o755;
+			buf.stx_size = 4096;
+			buf.stx_nlink = 2;
+			buf.stx_uid = 1000;
+			buf.stx_gid = 1000;
+			buf.stx_ino = 42;
+			buf.stx_dev_major = 8;
+			buf.stx_dev_minor = 1;
+			buf.stx_rdev_major = 0;
+			buf.stx_rdev_minor = 0;
+			buf.stx_mtime = statx_timestamp(1_700_000_000, 0);
+			buf.stx_btime = statx_timestamp(1_600_000_000, 0);
+
+			let cha = from_statx(buf);
+			assert!(cha.is_dir());
+			assert_eq!(cha.len, 4096);
+			assert_eq!(cha.mode, libc::S_IFDIR as libc::mode_t | 0o755);
+			assert_eq!(cha.dev, libc::makedev(8, 1));
+			assert_eq!(cha.ino, 42);
+			assert_eq!(cha.nlink, 2);
+			assert_eq!(cha.uid, 1000);
+			assert_eq!(cha.gid, 1000);
+			assert!(cha.mtime.is_some());
+			// `STATX_BTIME` wasn't set in `stx_mask`, so `btime` must stay `None`
+			// even though `stx_btime` itself holds a plausible value.
+			assert_eq!(cha.btime, None);
+		}
+
+		#[test]
+		fn from_statx_reports_btime_when_mask_bit_set() {
+			let mut buf: libc::statx = unsafe { MaybeUninit::zeroed().assume_init() };
+			buf.stx_mask = libc::STATX_BASIC_STATS | libc::STATX_BTIME;
+			buf.stx_mode = libc::S_IFREG as u16 | 0o644;
+			buf.stx_btime = statx_timestamp(1_600_000_000, 0);
+
+			let cha = from_statx(buf);
+			assert!(cha.btime.is_some());
+		}
+	}
+
+	pub(super) mod xattr {
+		use std::path::Path;
+
+		use super::super::{Acl, AclEntry, AclTag, ChaXattr, FileCapabilities};
+
+		// POSIX ACL xattr wire format (`linux/posix_acl_xattr.h`).
+		const ACL_XATTR_VERSION: u32 = 0x0002;
+		const ACL_UNDEFINED_ID: u32 = u32::MAX;
+
+		// `vfs_cap_data`, versions 2 and 3 (`linux/capability.h`).
+		const VFS_CAP_REVISION_2: u32 = 0x02000000;
+		const VFS_CAP_REVISION_3: u32 = 0x03000000;
+		const VFS_CAP_FLAGS_EFFECTIVE: u32 = 0x000001;
+
+		/// Fetches every extended attribute on `path`, decoding the ACL and
+		/// capability ones and keeping `user.*` pairs raw; attributes in any
+		/// other namespace are skipped. Returns `None` if the file has none,
+		/// or the filesystem doesn't support xattrs at all.
+		pub(in super::super) fn fetch(path: &Path) -> Option<ChaXattr> {
+			let names = list(path)?;
+			if names.is_empty() {
+				return None;
+			}
+
+			let mut xattr = ChaXattr::default();
+			for name in names {
+				let Some(value) = get(path, &name) else { continue };
+				match name.as_slice() {
+					b"system.posix_acl_access" => xattr.acl_access = decode_acl(&value),
+					b"system.posix_acl_default" => xattr.acl_default = decode_acl(&value),
+					b"security.capability" => xattr.fcaps = decode_fcaps(&value),
+					_ => {
+						if let Some(key) = name.strip_prefix(b"user.") {
+							xattr.user.push((key.to_vec(), value));
+						}
+					}
+				}
+			}
+			Some(xattr)
+		}
+
+		fn list(path: &Path) -> Option<Vec<Vec<u8>>> {
+			use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+			let c_path = CString::new(path.as_os_str().as_bytes()).ok()?;
+
+			let size = unsafe { libc::listxattr(c_path.as_ptr(), std::ptr::null_mut(), 0) };
+			if size <= 0 {
+				return if size == 0 { Some(Vec::new()) } else { None };
+			}
+
+			let mut buf = vec![0u8; size as usize];
+			let written = unsafe {
+				libc::listxattr(c_path.as_ptr(), buf.as_mut_ptr() as *mut libc::c_char, buf.len())
+			};
+			if written < 0 {
+				return None;
+			}
+			buf.truncate(written as usize);
+
+			Some(buf.split(|&b| b == 0).filter(|s| !s.is_empty()).map(<[u8]>::to_vec).collect())
+		}
+
+		fn get(path: &Path, name: &[u8]) -> Option<Vec<u8>> {
+			use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+			let c_path = CString::new(path.as_os_str().as_bytes()).ok()?;
+			let c_name = CString::new(name).ok()?;
+
+			let size =
+				unsafe { libc::getxattr(c_path.as_ptr(), c_name.as_ptr(), std::ptr::null_mut(), 0) };
+			if size < 0 {
+				return None;
+			}
+
+			let mut buf = vec![0u8; size as usize];
+			let written = unsafe {
+				libc::getxattr(
+					c_path.as_ptr(),
+					c_name.as_ptr(),
+					buf.as_mut_ptr() as *mut libc::c_void,
+					buf.len(),
+				)
+			};
+			if written < 0 {
+				return None;
+			}
+			buf.truncate(written as usize);
+			Some(buf)
+		}
+
+		fn decode_acl(buf: &[u8]) -> Option<Acl> {
+			if buf.len() < 4 {
+				return None;
+			}
+			if u32::from_le_bytes(buf[..4].try_into().ok()?) != ACL_XATTR_VERSION {
+				return None;
+			}
+
+			let mut entries = Vec::new();
+			for chunk in buf[4..].chunks_exact(8) {
+				let e_tag = u16::from_le_bytes([chunk[0], chunk[1]]);
+				let e_perm = u16::from_le_bytes([chunk[2], chunk[3]]);
+				let e_id = u32::from_le_bytes(chunk[4..8].try_into().ok()?);
+
+				let tag = match e_tag {
+					0x01 => AclTag::UserObj,
+					0x02 => AclTag::User,
+					0x04 => AclTag::GroupObj,
+					0x08 => AclTag::Group,
+					0x10 => AclTag::Mask,
+					0x20 => AclTag::Other,
+					_ => continue,
+				};
+				let id = if e_id == ACL_UNDEFINED_ID { 0 } else { e_id };
+				entries.push(AclEntry { tag, id, perm: e_perm as u8 });
+			}
+			Some(Acl { entries })
+		}
+
+		fn decode_fcaps(buf: &[u8]) -> Option<FileCapabilities> {
+			if buf.len() < 4 {
+				return None;
+			}
+			let magic_etc = u32::from_le_bytes(buf[..4].try_into().ok()?);
+			let version = magic_etc & 0xff000000;
+
+			if version != VFS_CAP_REVISION_2 && version != VFS_CAP_REVISION_3 {
+				return None;
+			}
+			if buf.len() < 20 {
+				return None;
+			}
+
+			let lo = |o: usize| u32::from_le_bytes(buf[o..o + 4].try_into().unwrap());
+			let permitted = lo(4) as u64 | ((lo(12) as u64) << 32);
+			let inheritable = lo(8) as u64 | ((lo(16) as u64) << 32);
+
+			let root_uid =
+				if version == VFS_CAP_REVISION_3 && buf.len() >= 24 { Some(lo(20)) } else { None };
+
+			Some(FileCapabilities {
+				permitted,
+				inheritable,
+				effective: magic_etc & VFS_CAP_FLAGS_EFFECTIVE != 0,
+				root_uid,
+			})
+		}
+
+		#[cfg(test)]
+		mod tests {
+			use super::*;
+
+			fn acl_bytes(entries: &[(u16, u16, u32)]) -> Vec<u8> {
+				let mut buf = ACL_XATTR_VERSION.to_le_bytes().to_vec();
+				for &(tag, perm, id) in entries {
+					buf.extend_from_slice(&tag.to_le_bytes());
+					buf.extend_from_slice(&perm.to_le_bytes());
+					buf.extend_from_slice(&id.to_le_bytes());
+				}
+				buf
+			}
+
+			#[test]
+			fn decode_acl_parses_entries_and_maps_undefined_id() {
+				let buf = acl_bytes(&[
+					(0x01, 0o6, ACL_UNDEFINED_ID),
+					(0x02, 0o4, 1000),
+					(0x20, 0o4, ACL_UNDEFINED_ID),
+				]);
+
+				let acl = decode_acl(&buf).unwrap();
+				assert_eq!(acl.entries, vec![
+					AclEntry { tag: AclTag::UserObj, id: 0, perm: 0o6 },
+					AclEntry { tag: AclTag::User, id: 1000, perm: 0o4 },
+					AclEntry { tag: AclTag::Other, id: 0, perm: 0o4 },
+				]);
+			}
+
+			#[test]
+			fn decode_acl_rejects_wrong_version() {
+				let mut buf = acl_bytes(&[(0x01, 0o6, ACL_UNDEFINED_ID)]);
+				buf[0] = 0xff;
+				assert_eq!(decode_acl(&buf), None);
+			}
+
+			#[test]
+			fn decode_acl_rejects_truncated_buffer() {
+				assert_eq!(decode_acl(&[0x02, 0x00]), None);
+			}
+
+			#[test]
+			fn decode_fcaps_v2() {
+				let mut buf = VFS_CAP_REVISION_2.to_le_bytes().to_vec();
+				buf.extend_from_slice(&1u32.to_le_bytes()); // permitted low
+				buf.extend_from_slice(&2u32.to_le_bytes()); // inheritable low
+				buf.extend_from_slice(&3u32.to_le_bytes()); // permitted high
+				buf.extend_from_slice(&4u32.to_le_bytes()); // inheritable high
+				buf[0..4].copy_from_slice(&(VFS_CAP_REVISION_2 | VFS_CAP_FLAGS_EFFECTIVE).to_le_bytes());
+
+				let fcaps = decode_fcaps(&buf).unwrap();
+				assert_eq!(fcaps.permitted, 1 | (3u64 << 32));
+				assert_eq!(fcaps.inheritable, 2 | (4u64 << 32));
+				assert!(fcaps.effective);
+				assert_eq!(fcaps.root_uid, None);
+			}
+
+			#[test]
+			fn decode_fcaps_v3_has_root_uid() {
+				let mut buf = (VFS_CAP_REVISION_3 | VFS_CAP_FLAGS_EFFECTIVE).to_le_bytes().to_vec();
+				buf.extend_from_slice(&1u32.to_le_bytes());
+				buf.extend_from_slice(&2u32.to_le_bytes());
+				buf.extend_from_slice(&3u32.to_le_bytes());
+				buf.extend_from_slice(&4u32.to_le_bytes());
+				buf.extend_from_slice(&1000u32.to_le_bytes()); // root_uid
+
+				let fcaps = decode_fcaps(&buf).unwrap();
+				assert_eq!(fcaps.root_uid, Some(1000));
+			}
+
+			#[test]
+			fn decode_fcaps_rejects_garbage_version() {
+				let mut buf = 0xdead_beefu32.to_le_bytes().to_vec();
+				buf.resize(20, 0);
+				assert_eq!(decode_fcaps(&buf), None);
+			}
+
+			#[test]
+			fn decode_fcaps_rejects_truncated_buffer() {
+				let mut buf = VFS_CAP_REVISION_2.to_le_bytes().to_vec();
+				buf.extend_from_slice(&1u32.to_le_bytes());
+				assert_eq!(decode_fcaps(&buf), None);
+			}
+		}
+	}
+}